@@ -5,7 +5,7 @@
 //!
 //! This module declares the data types used to represent external functions and call signatures.
 
-use ir::{ArgumentLoc, ExternalName, SigRef, Type};
+use ir::{types, ArgumentLoc, ExternalName, SigRef, Type};
 use isa::{RegInfo, RegUnit};
 use settings::CallConv;
 use std::cmp;
@@ -36,6 +36,24 @@ pub struct Signature {
     /// This can be computed from the legalized `params` array as the maximum (offset plus
     /// byte size) of the `ArgumentLoc::Stack(offset)` argument.
     pub argument_bytes: Option<u32>,
+
+    /// The number of bytes of stack space the calling convention requires the caller to
+    /// reserve in its own frame for this call, on top of `argument_bytes`.
+    ///
+    /// For example, the Win64 fastcall convention requires the caller to reserve a 32-byte
+    /// "shadow space" the callee may use to spill its register arguments, even though that
+    /// space holds no argument of its own. `None` before legalization; set to `Some(0)` by
+    /// `compute_argument_bytes` for conventions that don't need any reserved space.
+    pub reserved_bytes: Option<u32>,
+
+    /// The original, pre-legalization `params` and `returns`, saved the first time a
+    /// legalization pass rewrites this signature.
+    ///
+    /// ABI legalization splits wide types, inserts hidden pointers, and assigns
+    /// `ArgumentLoc`s, which loses the user-facing parameter and return lists. Call and
+    /// return instruction legalization need both the "before" and "after" views to map
+    /// values correctly, so the first mutation stashes a copy of `self` here.
+    pub old_signature: Option<Box<Signature>>,
 }
 
 impl Signature {
@@ -46,6 +64,8 @@ impl Signature {
             returns: Vec::new(),
             call_conv,
             argument_bytes: None,
+            reserved_bytes: None,
+            old_signature: None,
         }
     }
 
@@ -55,12 +75,61 @@ impl Signature {
         self.returns.clear();
         self.call_conv = call_conv;
         self.argument_bytes = None;
+        self.reserved_bytes = None;
+        self.old_signature = None;
+    }
+
+    /// Has this signature been legalized for a specific ISA yet?
+    ///
+    /// This is distinct from checking `argument_bytes.is_some()`: `argument_bytes` is only
+    /// set by `compute_argument_bytes`, while `legalized()` becomes true only once a
+    /// legalization pass rewrites `params` or `returns`, such as `legalize_returns`.
+    pub fn legalized(&self) -> bool {
+        self.old_signature.is_some()
+    }
+
+    /// The original parameters, from before legalization rewrote `params`.
+    ///
+    /// Returns `params` itself if the signature hasn't been legalized yet.
+    pub fn old_params(&self) -> &[AbiParam] {
+        match self.old_signature {
+            Some(ref old) => &old.params,
+            None => &self.params,
+        }
+    }
+
+    /// The original return values, from before legalization rewrote `returns`.
+    ///
+    /// Returns `returns` itself if the signature hasn't been legalized yet.
+    pub fn old_returns(&self) -> &[AbiParam] {
+        match self.old_signature {
+            Some(ref old) => &old.returns,
+            None => &self.returns,
+        }
+    }
+
+    /// Save a snapshot of the current `params` and `returns` into `old_signature`, unless
+    /// one has already been saved.
+    ///
+    /// Legalization passes that rewrite `params`/`returns` should call this before mutating
+    /// them, so the pre-legalization views remain available.
+    fn save_old_signature(&mut self) {
+        if self.old_signature.is_none() {
+            let mut old = self.clone();
+            old.old_signature = None;
+            self.old_signature = Some(Box::new(old));
+        }
     }
 
-    /// Compute the size of the stack arguments and mark signature as legalized.
+    /// Compute the size of the stack arguments.
     ///
-    /// Even if there are no stack arguments, this will set `params` to `Some(0)` instead
-    /// of `None`. This indicates that the signature has been legalized.
+    /// Even if there are no stack arguments, this will set `argument_bytes` to `Some(0)`
+    /// instead of `None`.
+    ///
+    /// This also fills in `reserved_bytes` according to `self.call_conv`. It does not touch
+    /// `old_signature`: computing byte offsets doesn't rewrite `params` or `returns`, so it
+    /// must not make `legalized()` return true on its own -- only a pass that actually
+    /// rewrites those lists (e.g. `legalize_returns`, `legalize_params`) does that.
     pub fn compute_argument_bytes(&mut self) {
         let bytes = self.params
             .iter()
@@ -72,6 +141,30 @@ impl Signature {
             })
             .fold(0, cmp::max);
         self.argument_bytes = Some(bytes);
+        self.reserved_bytes = Some(Self::call_conv_reserved_bytes(self.call_conv));
+    }
+
+    /// The number of bytes of caller-reserved stack space required by `call_conv`, regardless
+    /// of how many arguments are actually passed on the stack.
+    fn call_conv_reserved_bytes(call_conv: CallConv) -> u32 {
+        match call_conv {
+            // Win64 fastcall requires the caller to reserve 32 bytes of "shadow space" for
+            // the callee to spill its four register arguments into, whether or not it does.
+            CallConv::Fastcall => 32,
+            _ => 0,
+        }
+    }
+
+    /// The total number of bytes of the caller's stack frame used by this call: the stack
+    /// argument area (`argument_bytes`) plus any convention-mandated reserved space
+    /// (`reserved_bytes`) that isn't itself an argument slot.
+    ///
+    /// Returns `None` until the signature has had `compute_argument_bytes` called on it.
+    pub fn total_stack_bytes(&self) -> Option<u32> {
+        match (self.argument_bytes, self.reserved_bytes) {
+            (Some(args), Some(reserved)) => Some(args + reserved),
+            _ => None,
+        }
     }
 
     /// Return an object that can display `self` with correct register names.
@@ -83,6 +176,84 @@ impl Signature {
     pub fn special_param_index(&self, purpose: ArgumentPurpose) -> Option<usize> {
         self.params.iter().rposition(|arg| arg.purpose == purpose)
     }
+
+    /// Does this signature have a parameter whose purpose is `StructReturn`?
+    ///
+    /// This is the hidden pointer a caller passes in so the callee can write a return value
+    /// that doesn't fit in the available return registers.
+    pub fn uses_struct_return_param(&self) -> bool {
+        self.params
+            .iter()
+            .any(|arg| arg.purpose == ArgumentPurpose::StructReturn)
+    }
+
+    /// Compute the number of registers of width `reg_width_bytes` needed to hold all of
+    /// `self.returns`, rounding each return value up to a whole number of registers.
+    pub fn num_return_registers_required(&self, reg_width_bytes: u32) -> usize {
+        self.returns
+            .iter()
+            .map(|ret| {
+                let bytes = ret.value_type.bytes();
+                ((bytes + reg_width_bytes - 1) / reg_width_bytes) as usize
+            })
+            .sum()
+    }
+
+    /// Legalize `self.returns` for a target that can only return values through
+    /// `num_regs` return registers of `reg_width_bytes` each.
+    ///
+    /// The leading return values that fit entirely within `num_regs` registers are left
+    /// alone. Once a return value would overflow the available registers, it and all
+    /// following return values are dropped from `self.returns` and instead written by the
+    /// callee through a hidden pointer, which is appended to `self.params` as
+    /// `AbiParam::special(pointer_type, purpose)`.
+    ///
+    /// Does nothing if all of `self.returns` already fits in `num_regs` registers.
+    pub fn legalize_returns(
+        &mut self,
+        num_regs: usize,
+        reg_width_bytes: u32,
+        pointer_type: Type,
+        purpose: ArgumentPurpose,
+    ) {
+        let mut regs_used = 0;
+        let mut split_at = self.returns.len();
+        for (idx, ret) in self.returns.iter().enumerate() {
+            let bytes = ret.value_type.bytes();
+            let regs = ((bytes + reg_width_bytes - 1) / reg_width_bytes) as usize;
+            if regs_used + regs > num_regs {
+                split_at = idx;
+                break;
+            }
+            regs_used += regs;
+        }
+
+        if split_at < self.returns.len() {
+            self.save_old_signature();
+            self.returns.truncate(split_at);
+            self.params.push(AbiParam::special(pointer_type, purpose));
+        }
+    }
+
+    /// Legalize `self.params`, assigning every `AbiParam` a location chosen by `assigner`.
+    ///
+    /// This is the normal entry point for running `legalize_args` over a signature: it
+    /// snapshots the pre-legalization `params` into `old_signature` first (unless one is
+    /// already saved), so `old_params()` keeps returning the original, unsplit list even
+    /// after `assigner` has split wide arguments and rewritten `self.params` in place.
+    pub fn legalize_params<A: ArgAssigner>(&mut self, assigner: &mut A) {
+        self.save_old_signature();
+        legalize_args(&mut self.params, assigner);
+    }
+
+    /// Legalize `self.returns`, assigning every `AbiParam` a location chosen by `assigner`.
+    ///
+    /// Like `legalize_params`, this snapshots `old_signature` before mutating `self.returns`
+    /// so `old_returns()` still shows the pre-legalization list afterward.
+    pub fn legalize_return_locations<A: ArgAssigner>(&mut self, assigner: &mut A) {
+        self.save_old_signature();
+        legalize_args(&mut self.returns, assigner);
+    }
 }
 
 /// Wrapper type capable of displaying a `Signature` with correct register names.
@@ -111,6 +282,10 @@ impl<'a> fmt::Display for DisplaySignature<'a> {
             write_list(f, &self.0.returns, self.1)?;
         }
         write!(f, " {}", self.0.call_conv)
+        // `reserved_bytes` is deliberately not printed here: it would add a new token to
+        // the textual IR format, and no reader change ships to parse it back. It's still
+        // reachable directly via the `reserved_bytes` field (and `Debug`) for anything
+        // that needs it without going through a print/parse roundtrip.
     }
 }
 
@@ -236,6 +411,98 @@ pub enum ArgumentExtension {
     Sext,
 }
 
+/// A conversion requested by an `ArgAssigner` when an argument can't be assigned a location
+/// as-is.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValueConversion {
+    /// Split the integer value into two halves, passed as separate arguments, low half
+    /// first.
+    IntSplit,
+    /// Split the vector value into its individual lanes, passed as separate arguments.
+    VectorSplit,
+    /// Extend the value to fill a full register, per the given `ArgumentExtension`.
+    Extend(ArgumentExtension),
+}
+
+/// The decision an `ArgAssigner` makes about where the next argument goes.
+pub enum ArgAction {
+    /// Assign the argument to the given register.
+    Reg(RegUnit),
+    /// Assign the argument to the next available stack slot. `legalize_args` tracks the
+    /// running stack offset; the assigner doesn't need to.
+    Stack,
+    /// The argument doesn't fit as a single value in a location; apply the requested
+    /// conversion and reconsider the (possibly now multiple) resulting arguments.
+    Convert(ValueConversion),
+}
+
+/// A pluggable policy for assigning `ArgumentLoc`s to a `Signature`'s parameters or returns.
+///
+/// ISA backends implement this to describe their own calling convention -- which registers
+/// are available, in what order, and when a value must be split or extended -- instead of
+/// reimplementing the legalization walk in `legalize_args`.
+pub trait ArgAssigner {
+    /// Decide where to place `arg`, the next parameter or return value in line.
+    fn assign(&mut self, arg: &AbiParam) -> ArgAction;
+}
+
+/// Legalize `args` in place, giving every `AbiParam` an `ArgumentLoc` chosen by `assigner`.
+///
+/// This drives `assigner` to a fixed point: whenever it requests a `ValueConversion`, the
+/// triggering argument is rewritten accordingly (an oversized integer becomes two, a vector
+/// becomes its lanes, a small integer gets its `extension` flag set) and reconsidered, so a
+/// single call can keep splitting a value until every piece fits in a register or on the
+/// stack. Arguments sent to the stack are placed at increasing offsets in declaration order;
+/// call `Signature::compute_argument_bytes` afterward to turn those offsets into a byte count.
+///
+/// `args` takes a `Vec` rather than a slice because splitting an argument grows the list.
+pub fn legalize_args<A: ArgAssigner>(args: &mut Vec<AbiParam>, assigner: &mut A) {
+    let mut stack_offset: i32 = 0;
+    let mut i = 0;
+
+    while i < args.len() {
+        match assigner.assign(&args[i]) {
+            ArgAction::Reg(regunit) => {
+                args[i].location = ArgumentLoc::Reg(regunit);
+                i += 1;
+            }
+            ArgAction::Stack => {
+                let bytes = args[i].value_type.bytes();
+                args[i].location = ArgumentLoc::Stack(stack_offset);
+                stack_offset += bytes as i32;
+                i += 1;
+            }
+            ArgAction::Convert(ValueConversion::Extend(ext)) => {
+                args[i].extension = ext;
+                // The extended value still needs a location; reconsider it.
+            }
+            ArgAction::Convert(ValueConversion::IntSplit) => {
+                let lo = args[i];
+                let ty = lo.value_type;
+                let half = ty.half_width()
+                    .unwrap_or_else(|| panic!("can't split {} into smaller integers", ty));
+                args[i].value_type = half;
+                let mut hi = lo;
+                hi.value_type = half;
+                args.insert(i + 1, hi);
+                // Reconsider the low half; it may need further splitting.
+            }
+            ArgAction::Convert(ValueConversion::VectorSplit) => {
+                let template = args[i];
+                let lane_type = template.value_type.lane_type();
+                let lane_count = template.value_type.lane_count();
+                args[i].value_type = lane_type;
+                let mut lane = template;
+                lane.value_type = lane_type;
+                for n in 1..lane_count {
+                    args.insert(i + n as usize, lane);
+                }
+                // Reconsider the first lane; it may need further conversion.
+            }
+        }
+    }
+}
+
 /// The special purpose of a function argument.
 ///
 /// Function arguments and return values are used to pass user program values between functions,
@@ -292,10 +559,27 @@ pub enum ArgumentPurpose {
     /// This is a special-purpose argument used to identify the calling convention expected by the
     /// caller in an indirect call. The callee can verify that the expected signature ID matches.
     SignatureId,
+
+    /// A stack probe size argument.
+    ///
+    /// This indicates the argument that carries the number of bytes to probe, passed to the
+    /// `Probestack` `LibCall`. It's marked so an ISA's `ArgAssigner` can route it to whatever
+    /// register that convention's stack probe expects, rather than the first free argument
+    /// register.
+    Probestack,
 }
 
 /// Text format names of the `ArgumentPurpose` variants.
-static PURPOSE_NAMES: [&str; 7] = ["normal", "sret", "link", "fp", "csr", "vmctx", "sigid"];
+static PURPOSE_NAMES: [&str; 8] = [
+    "normal",
+    "sret",
+    "link",
+    "fp",
+    "csr",
+    "vmctx",
+    "sigid",
+    "probestack",
+];
 
 impl fmt::Display for ArgumentPurpose {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -314,11 +598,136 @@ impl FromStr for ArgumentPurpose {
             "csr" => Ok(ArgumentPurpose::CalleeSaved),
             "vmctx" => Ok(ArgumentPurpose::VMContext),
             "sigid" => Ok(ArgumentPurpose::SignatureId),
+            "probestack" => Ok(ArgumentPurpose::Probestack),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A well-known runtime routine the code generator may need to call implicitly, without the
+/// frontend ever having declared it.
+///
+/// These calls are emitted during legalization, e.g. to move overlarge data or to implement a
+/// floating-point rounding mode the target ISA doesn't have an instruction for. Unlike a
+/// frontend-declared external function, a `LibCall` carries no signature of its own; call
+/// `signature()` to build one for a given calling convention and pointer type.
+///
+/// This only covers naming the call and building its `Signature`. Turning a `LibCall` into
+/// something the preamble can actually declare -- an `ExternalName` a legalizer can stuff into
+/// an `ExtFuncData` -- needs `ExternalName` (defined outside this module) to grow a variant
+/// that holds a `LibCall`. That wiring doesn't exist yet and is out of scope here; until it
+/// lands, callers build a `LibCall`'s `Signature` and name it themselves, the same way they
+/// would for any other `ExternalName::User` call.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum LibCall {
+    /// probe for stack overflow. Takes the number of bytes requested to allocate.
+    Probestack,
+    /// memcpy
+    Memcpy,
+    /// memmove
+    Memmove,
+    /// memset
+    Memset,
+    /// ceil.f32
+    CeilF32,
+    /// ceil.f64
+    CeilF64,
+    /// floor.f32
+    FloorF32,
+    /// floor.f64
+    FloorF64,
+    /// trunc.f32
+    TruncF32,
+    /// trunc.f64
+    TruncF64,
+    /// nearest.f32
+    NearestF32,
+    /// nearest.f64
+    NearestF64,
+}
+
+/// Text format names of the `LibCall` variants.
+static LIBCALL_NAMES: [&str; 12] = [
+    "probestack",
+    "memcpy",
+    "memmove",
+    "memset",
+    "ceil.f32",
+    "ceil.f64",
+    "floor.f32",
+    "floor.f64",
+    "trunc.f32",
+    "trunc.f64",
+    "nearest.f32",
+    "nearest.f64",
+];
+
+impl fmt::Display for LibCall {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(LIBCALL_NAMES[*self as usize])
+    }
+}
+
+impl FromStr for LibCall {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, ()> {
+        use self::LibCall::*;
+        match s {
+            "probestack" => Ok(Probestack),
+            "memcpy" => Ok(Memcpy),
+            "memmove" => Ok(Memmove),
+            "memset" => Ok(Memset),
+            "ceil.f32" => Ok(CeilF32),
+            "ceil.f64" => Ok(CeilF64),
+            "floor.f32" => Ok(FloorF32),
+            "floor.f64" => Ok(FloorF64),
+            "trunc.f32" => Ok(TruncF32),
+            "trunc.f64" => Ok(TruncF64),
+            "nearest.f32" => Ok(NearestF32),
+            "nearest.f64" => Ok(NearestF64),
             _ => Err(()),
         }
     }
 }
 
+impl LibCall {
+    /// Build the signature for a call to `self` under `call_conv`.
+    ///
+    /// `pointer_type` is used for any pointer-sized arguments or return values.
+    pub fn signature(self, call_conv: CallConv, pointer_type: Type) -> Signature {
+        let mut sig = Signature::new(call_conv);
+
+        match self {
+            LibCall::Probestack => {
+                sig.params
+                    .push(AbiParam::special(pointer_type, ArgumentPurpose::Probestack));
+            }
+            LibCall::Memcpy | LibCall::Memmove => {
+                sig.params.push(AbiParam::new(pointer_type));
+                sig.params.push(AbiParam::new(pointer_type));
+                sig.params.push(AbiParam::new(pointer_type));
+                sig.returns.push(AbiParam::new(pointer_type));
+            }
+            LibCall::Memset => {
+                sig.params.push(AbiParam::new(pointer_type));
+                sig.params.push(AbiParam::new(types::I32));
+                sig.params.push(AbiParam::new(pointer_type));
+                sig.returns.push(AbiParam::new(pointer_type));
+            }
+            LibCall::CeilF32 | LibCall::FloorF32 | LibCall::TruncF32 | LibCall::NearestF32 => {
+                sig.params.push(AbiParam::new(types::F32));
+                sig.returns.push(AbiParam::new(types::F32));
+            }
+            LibCall::CeilF64 | LibCall::FloorF64 | LibCall::TruncF64 | LibCall::NearestF64 => {
+                sig.params.push(AbiParam::new(types::F64));
+                sig.returns.push(AbiParam::new(types::F64));
+            }
+        }
+
+        sig
+    }
+}
+
 /// An external function.
 ///
 /// Information about a function that can be called directly with a direct `call` instruction.
@@ -346,7 +755,7 @@ impl fmt::Display for ExtFuncData {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ir::types::{B8, F32, I32};
+    use ir::types::{B8, F32, F64, I32, I64, I8};
     use std::string::ToString;
 
     #[test]
@@ -376,6 +785,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn libcall_names() {
+        for (&lc, &n) in [
+            LibCall::Probestack,
+            LibCall::Memcpy,
+            LibCall::Memmove,
+            LibCall::Memset,
+            LibCall::CeilF32,
+            LibCall::CeilF64,
+            LibCall::FloorF32,
+            LibCall::FloorF64,
+            LibCall::TruncF32,
+            LibCall::TruncF64,
+            LibCall::NearestF32,
+            LibCall::NearestF64,
+        ].iter()
+            .zip(LIBCALL_NAMES.iter())
+        {
+            assert_eq!(lc.to_string(), n);
+            assert_eq!(Ok(lc), n.parse());
+        }
+    }
+
+    #[test]
+    fn libcall_signatures() {
+        let mem = LibCall::Memcpy.signature(CallConv::SystemV, I32);
+        assert_eq!(mem.params.len(), 3);
+        assert_eq!(mem.returns.len(), 1);
+
+        let round = LibCall::FloorF64.signature(CallConv::SystemV, I32);
+        assert_eq!(round.params.len(), 1);
+        assert_eq!(round.params[0].value_type, F64);
+        assert_eq!(round.returns[0].value_type, F64);
+    }
+
     #[test]
     fn call_conv() {
         for &cc in &[
@@ -420,4 +864,140 @@ mod tests {
             "(i32 [24], i32x4 [8]) -> f32, b8 baldrdash"
         );
     }
+
+    #[test]
+    fn reserved_stack_bytes() {
+        // Most conventions don't reserve any caller-side stack space of their own.
+        let mut sig = Signature::new(CallConv::SystemV);
+        assert_eq!(sig.total_stack_bytes(), None);
+        sig.compute_argument_bytes();
+        assert_eq!(sig.reserved_bytes, Some(0));
+        assert_eq!(sig.total_stack_bytes(), Some(0));
+
+        // Win64 fastcall reserves 32 bytes of shadow space on top of stack arguments.
+        let mut sig = Signature::new(CallConv::Fastcall);
+        sig.params.push(AbiParam::new(I32));
+        sig.params[0].location = ArgumentLoc::Stack(0);
+        sig.compute_argument_bytes();
+        assert_eq!(sig.argument_bytes, Some(4));
+        assert_eq!(sig.reserved_bytes, Some(32));
+        assert_eq!(sig.total_stack_bytes(), Some(36));
+    }
+
+    #[test]
+    fn compute_argument_bytes_does_not_legalize() {
+        // Computing byte offsets doesn't rewrite `params`/`returns`, so it must not make
+        // `legalized()` report true or disturb `old_params()`/`old_returns()`.
+        let mut sig = Signature::new(CallConv::SystemV);
+        sig.params.push(AbiParam::new(I32));
+        sig.params[0].location = ArgumentLoc::Stack(0);
+
+        sig.compute_argument_bytes();
+        assert!(!sig.legalized());
+        assert_eq!(sig.old_params().len(), sig.params.len());
+    }
+
+    #[test]
+    fn multi_value_returns() {
+        let mut sig = Signature::new(CallConv::SystemV);
+        sig.returns.push(AbiParam::new(I32));
+        sig.returns.push(AbiParam::new(I32));
+        sig.returns.push(AbiParam::new(I32));
+
+        assert_eq!(sig.num_return_registers_required(8), 3);
+        assert!(!sig.uses_struct_return_param());
+
+        // Only 2 return registers available: the 3rd return value spills through a
+        // hidden pointer parameter, and the first two are left in `returns`.
+        assert!(!sig.legalized());
+        sig.legalize_returns(2, 8, I32, ArgumentPurpose::StructReturn);
+        assert_eq!(sig.returns.len(), 2);
+        assert_eq!(sig.params.len(), 1);
+        assert_eq!(sig.params[0].purpose, ArgumentPurpose::StructReturn);
+        assert!(sig.uses_struct_return_param());
+
+        // The pre-legalization return list is still available.
+        assert!(sig.legalized());
+        assert_eq!(sig.old_returns().len(), 3);
+        assert_eq!(sig.old_params().len(), 0);
+
+        // Legalizing again with enough registers for what remains is a no-op.
+        sig.legalize_returns(2, 8, I32, ArgumentPurpose::StructReturn);
+        assert_eq!(sig.returns.len(), 2);
+        assert_eq!(sig.params.len(), 1);
+        assert_eq!(sig.old_returns().len(), 3);
+    }
+
+    /// A toy assigner for a 32-bit target with 2 integer argument registers: it splits
+    /// 64-bit integers into register-sized halves and zero-extends anything smaller.
+    struct Toy32BitAssigner {
+        regs_used: usize,
+    }
+
+    impl ArgAssigner for Toy32BitAssigner {
+        fn assign(&mut self, arg: &AbiParam) -> ArgAction {
+            if arg.value_type == I64 {
+                return ArgAction::Convert(ValueConversion::IntSplit);
+            }
+            if arg.value_type.bits() < 32 && arg.extension == ArgumentExtension::None {
+                return ArgAction::Convert(ValueConversion::Extend(ArgumentExtension::Uext));
+            }
+            if self.regs_used < 2 {
+                self.regs_used += 1;
+                ArgAction::Reg(self.regs_used as RegUnit - 1)
+            } else {
+                ArgAction::Stack
+            }
+        }
+    }
+
+    #[test]
+    fn arg_assigner_legalizes_to_fixed_point() {
+        let mut params = vec![AbiParam::new(I8), AbiParam::new(I64), AbiParam::new(I32)];
+        let mut assigner = Toy32BitAssigner { regs_used: 0 };
+        legalize_args(&mut params, &mut assigner);
+
+        // `i8` picked up a `uext` flag and then a register.
+        assert_eq!(params[0].value_type, I8);
+        assert_eq!(params[0].extension, ArgumentExtension::Uext);
+        assert_eq!(params[0].location, ArgumentLoc::Reg(0));
+
+        // `i64` split into two `i32` halves: the low half takes the remaining register,
+        // and the high half -- no registers left -- spills to the stack.
+        assert_eq!(params[1].value_type, I32);
+        assert_eq!(params[1].location, ArgumentLoc::Reg(1));
+        assert_eq!(params[2].value_type, I32);
+        assert_eq!(params[2].location, ArgumentLoc::Stack(0));
+
+        // The original third argument spills to the stack too, after the split halves.
+        assert_eq!(params[3].value_type, I32);
+        assert_eq!(params[3].location, ArgumentLoc::Stack(4));
+    }
+
+    #[test]
+    fn legalize_params_preserves_old_signature() {
+        let mut sig = Signature::new(CallConv::SystemV);
+        sig.params.push(AbiParam::new(I8));
+        sig.params.push(AbiParam::new(I64));
+
+        let mut assigner = Toy32BitAssigner { regs_used: 0 };
+        sig.legalize_params(&mut assigner);
+
+        // The live `params` reflect the split/extended arguments...
+        assert_eq!(sig.params.len(), 3);
+        assert_eq!(sig.params[0].value_type, I8);
+        assert_eq!(sig.params[1].value_type, I32);
+        assert_eq!(sig.params[2].value_type, I32);
+
+        // ...but `old_params()` still shows the pre-legalization list, not the split one.
+        assert!(sig.legalized());
+        assert_eq!(sig.old_params().len(), 2);
+        assert_eq!(sig.old_params()[0].value_type, I8);
+        assert_eq!(sig.old_params()[1].value_type, I64);
+
+        // Running `compute_argument_bytes` afterward must not clobber that snapshot.
+        sig.compute_argument_bytes();
+        assert_eq!(sig.old_params().len(), 2);
+        assert_eq!(sig.old_params()[1].value_type, I64);
+    }
 }